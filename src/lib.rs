@@ -1,20 +1,123 @@
-use std::{error::Error, fmt::Display, fs, path::PathBuf};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    sync::{atomic::{AtomicU32, Ordering}, mpsc::channel, Mutex},
+};
 
 use chrono::{DateTime, Duration, Local};
 use clap::{Command, Arg};
-use image::{io::Reader};
+use filetime::{set_file_mtime, FileTime};
+use image::{io::Reader, imageops::FilterType, DynamicImage};
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Any,
+}
+
+impl std::str::FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "landscape" => Ok(Orientation::Landscape),
+            "portrait" => Ok(Orientation::Portrait),
+            "any" => Ok(Orientation::Any),
+            other => Err(format!("Invalid orientation '{}'. Expected 'landscape', 'portrait' or 'any'", other)),
+        }
+    }
+}
+
+impl Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Orientation::Landscape => "landscape",
+            Orientation::Portrait => "portrait",
+            Orientation::Any => "any",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+fn matches_orientation(orientation: Orientation, width: u32, height: u32) -> bool {
+    let landscape = width >= height;
+    match orientation {
+        Orientation::Landscape => landscape,
+        Orientation::Portrait => !landscape,
+        Orientation::Any => true,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SaveOutcome {
+    Saved,
+    SkippedTooSmall,
+    SkippedWrongOrientation,
+    SkippedDuplicate,
+    SkippedOther,
+}
 
 #[derive(Debug)]
 pub struct Config {
     target: PathBuf,
     verbose: bool,
     archive: bool,
+    watch: bool,
+    threads: usize,
+    dedupe: bool,
+    distance: u32,
+    min_width: u32,
+    min_height: u32,
+    orientation: Orientation,
+    split_orientation: bool,
+    dry_run: bool,
 }
 
 impl Display for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[target = {}, verbose = {}, archive = {}]", self.target.display(), self.verbose, self.archive)
+        write!(f, "[target = {}, verbose = {}, archive = {}, watch = {}, threads = {}, dedupe = {}, distance = {}, min_width = {}, min_height = {}, orientation = {}, split_orientation = {}, dry_run = {}]", self.target.display(), self.verbose, self.archive, self.watch, self.threads, self.dedupe, self.distance, self.min_width, self.min_height, self.orientation, self.split_orientation, self.dry_run)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FileConfig {
+    target: Option<PathBuf>,
+    verbose: Option<bool>,
+    archive: Option<bool>,
+    threads: Option<usize>,
+    min_width: Option<u32>,
+    min_height: Option<u32>,
+    orientation: Option<String>,
+}
+
+fn load_file_config(config_path: Option<PathBuf>) -> Result<FileConfig, String> {
+    let path = match config_path {
+        Some(path) => path,
+        None => {
+            let home_dir = home::home_dir().unwrap();
+            home_dir.join(".config").join("spotlight_save.toml")
+        }
+    };
+
+    if !path.is_file() {
+        return Ok(FileConfig::default());
     }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Can not read config file '{}': {}", path.display(), e))?;
+    toml::from_str(&content).map_err(|e| format!("Can not parse config file '{}': {}", path.display(), e))
+}
+
+/// Resolves an option's value, preferring the CLI value, then the config file value,
+/// then the given default.
+fn resolve_option<T>(cli: Option<T>, file: Option<T>, default: T) -> T {
+    cli.or(file).unwrap_or(default)
 }
 
 impl Config {
@@ -34,13 +137,89 @@ impl Config {
                             .short('a')
                             .long("archive")
                             .help("Archive images by year"))
+                        .arg(Arg::new("watch")
+                            .short('w')
+                            .long("watch")
+                            .help("Watch the spotlight dir continuously and save new images as they appear"))
+                        .arg(Arg::new("threads")
+                            .short('t')
+                            .long("threads")
+                            .takes_value(true)
+                            .help("Number of worker threads to use. Default is the number of CPU cores"))
+                        .arg(Arg::new("dedupe")
+                            .long("dedupe")
+                            .help("Skip images that are near-duplicates of images already saved"))
+                        .arg(Arg::new("distance")
+                            .long("distance")
+                            .takes_value(true)
+                            .help("Hamming distance threshold below which two images are considered duplicates. Default is 5"))
+                        .arg(Arg::new("config")
+                            .long("config")
+                            .takes_value(true)
+                            .help("Path to a config file. Default is '${HOME}/.config/spotlight_save.toml'"))
+                        .arg(Arg::new("min-width")
+                            .long("min-width")
+                            .takes_value(true)
+                            .help("Minimum image width to save. Default is 800"))
+                        .arg(Arg::new("min-height")
+                            .long("min-height")
+                            .takes_value(true)
+                            .help("Minimum image height to save. Default is 600"))
+                        .arg(Arg::new("orientation")
+                            .long("orientation")
+                            .takes_value(true)
+                            .help("Only save images with this orientation: 'landscape', 'portrait' or 'any'. Default is 'landscape'"))
+                        .arg(Arg::new("split-by-orientation")
+                            .long("split-by-orientation")
+                            .help("Save landscape and portrait images into separate 'landscape'/'portrait' subdirectories of the target dir"))
+                        .arg(Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("Report what would be saved/archived without touching disk"))
                         .get_matches();
 
-        let verbose = matches.is_present("verbose");
-        let archive = matches.is_present("archive");
+        let file_config = load_file_config(matches.value_of("config").map(PathBuf::from))?;
+
+        let verbose = matches.is_present("verbose") || file_config.verbose.unwrap_or(false);
+        let archive = matches.is_present("archive") || file_config.archive.unwrap_or(false);
+        let watch = matches.is_present("watch");
+        let dedupe = matches.is_present("dedupe");
+
+        let cli_threads = matches.value_of("threads")
+            .map(|v| v.parse::<usize>().map_err(|e| format!("Invalid value for '--threads': {}", e)))
+            .transpose()?;
+        let threads = resolve_option(cli_threads, file_config.threads, std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+
+        let distance = if let Some(distance) = matches.value_of("distance") {
+            distance.parse::<u32>().map_err(|e| format!("Invalid value for '--distance': {}", e))?
+        } else {
+            5
+        };
+
+        let cli_min_width = matches.value_of("min-width")
+            .map(|v| v.parse::<u32>().map_err(|e| format!("Invalid value for '--min-width': {}", e)))
+            .transpose()?;
+        let min_width = resolve_option(cli_min_width, file_config.min_width, 800);
+
+        let cli_min_height = matches.value_of("min-height")
+            .map(|v| v.parse::<u32>().map_err(|e| format!("Invalid value for '--min-height': {}", e)))
+            .transpose()?;
+        let min_height = resolve_option(cli_min_height, file_config.min_height, 600);
+
+        let orientation = if let Some(orientation) = matches.value_of("orientation") {
+            orientation.parse::<Orientation>()?
+        } else if let Some(orientation) = &file_config.orientation {
+            orientation.parse::<Orientation>()?
+        } else {
+            Orientation::Landscape
+        };
+
+        let split_orientation = matches.is_present("split-by-orientation");
+        let dry_run = matches.is_present("dry-run");
 
         let target = if let Some(dir) = matches.value_of("DIR") {
             PathBuf::from(dir)
+        } else if let Some(dir) = file_config.target {
+            dir
         } else {
             let home_dir = home::home_dir().unwrap();
             let picture_dir = home_dir.join("Pictures");
@@ -61,6 +240,15 @@ impl Config {
             target,
             verbose,
             archive,
+            watch,
+            threads,
+            dedupe,
+            distance,
+            min_width,
+            min_height,
+            orientation,
+            split_orientation,
+            dry_run,
         })
     }
 }
@@ -75,6 +263,14 @@ macro_rules! log {
 }
 
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(config.threads)
+        .build_global()?;
+
+    if config.watch {
+        return watch_images(&config);
+    }
+
     save_images(&config)?;
     if config.archive {
         archive_images(&config)?;
@@ -113,65 +309,253 @@ fn save_images(config: &Config) -> Result<(), Box<dyn Error>> {
     let spotlight_dir = get_spotlight_dir()?;
     log!(config.verbose, "Scan spotlight dir: {}", spotlight_dir.display());
 
-    let mut count = 0;
-    for entry in spotlight_dir.read_dir()? {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
+    let files: Vec<PathBuf> = spotlight_dir.read_dir()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+
+    let hashes = Mutex::new(if config.dedupe { load_target_hashes(config) } else { HashSet::new() });
+
+    let saved = AtomicU32::new(0);
+    let skipped_too_small = AtomicU32::new(0);
+    let skipped_wrong_orientation = AtomicU32::new(0);
+    let skipped_duplicate = AtomicU32::new(0);
+    let skipped_other = AtomicU32::new(0);
+
+    files.par_iter().for_each(|path| {
+        let counter = match save_image(config, path, &hashes) {
+            SaveOutcome::Saved => &saved,
+            SaveOutcome::SkippedTooSmall => &skipped_too_small,
+            SaveOutcome::SkippedWrongOrientation => &skipped_wrong_orientation,
+            SaveOutcome::SkippedDuplicate => &skipped_duplicate,
+            SaveOutcome::SkippedOther => &skipped_other,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+
+    if config.dedupe && !config.dry_run {
+        save_target_hashes(config, &hashes.into_inner().unwrap());
+    }
+
+    let saved = saved.load(Ordering::Relaxed);
+    if config.dry_run {
+        println!(
+            "Dry run summary: {} would-save, {} skipped-too-small, {} skipped-wrong-orientation, {} skipped-duplicate, {} skipped-other",
+            saved, skipped_too_small.load(Ordering::Relaxed), skipped_wrong_orientation.load(Ordering::Relaxed), skipped_duplicate.load(Ordering::Relaxed), skipped_other.load(Ordering::Relaxed)
+        );
+    } else {
+        log!(config.verbose, "{} images saved!", saved);
+    }
+
+    Ok(())
+}
+
+fn dhash_cache_path(config: &Config) -> PathBuf {
+    config.target.join(".dhash_cache")
+}
+
+fn load_target_hashes(config: &Config) -> HashSet<u64> {
+    let cache_path = dhash_cache_path(config);
+    if let Ok(content) = fs::read_to_string(&cache_path) {
+        let hashes: HashSet<u64> = content.lines()
+            .filter_map(|line| u64::from_str_radix(line.trim(), 16).ok())
+            .collect();
+        if !hashes.is_empty() {
+            return hashes;
         }
+    }
+
+    let mut hashes = HashSet::new();
+    for dir in target_scan_dirs(config) {
+        if let Ok(entries) = dir.read_dir() {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if !path.is_file() {
+                    continue;
+                }
 
-        if save_image(config, &path) {
-            count += 1;
+                if let Some(image) = decode_image(&path) {
+                    hashes.insert(dhash(&image));
+                }
+            }
         }
     }
 
-    log!(config.verbose, "{} images saved!", count);
+    hashes
+}
 
-    Ok(())
+fn target_scan_dirs(config: &Config) -> Vec<PathBuf> {
+    if config.split_orientation {
+        vec![
+            config.target.join("landscape"),
+            config.target.join("portrait"),
+        ]
+    } else {
+        vec![config.target.clone()]
+    }
+}
+
+fn save_target_hashes(config: &Config, hashes: &HashSet<u64>) {
+    let content = hashes.iter().map(|hash| format!("{:016x}", hash)).collect::<Vec<_>>().join("\n");
+    let _ = fs::write(dhash_cache_path(config), content);
 }
 
-fn save_image(config: &Config, filepath: &PathBuf) -> bool {
+fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash: u64 = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    hash
+}
+
+fn watch_images(config: &Config) -> Result<(), Box<dyn Error>> {
+    let spotlight_dir = get_spotlight_dir()?;
+    log!(config.verbose, "Watching spotlight dir: {}", spotlight_dir.display());
+
+    let hashes = Mutex::new(if config.dedupe { load_target_hashes(config) } else { HashSet::new() });
+
+    let (tx, rx) = channel();
+    let mut watcher: RecommendedWatcher = Watcher::new(tx, std::time::Duration::from_secs(2))?;
+    watcher.watch(&spotlight_dir, RecursiveMode::NonRecursive)?;
+
+    loop {
+        match rx.recv() {
+            Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Write(path)) => {
+                if path.is_file() && save_image(config, &path, &hashes) == SaveOutcome::Saved {
+                    log!(config.verbose, "Saved new image: {}", path.display());
+                }
+            }
+            Ok(DebouncedEvent::Error(e, path)) => {
+                eprintln!("Watch error on {:?}: {}", path, e);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                // The watcher thread died and the channel is now disconnected, so
+                // recv() would return this same error forever. Bail out instead of
+                // spinning a dead loop at 100% CPU.
+                eprintln!("Watcher disconnected: {}", e);
+                return Err(Box::new(e));
+            }
+        }
+    }
+}
+
+fn decode_image(filepath: &PathBuf) -> Option<DynamicImage> {
+    let reader = Reader::open(filepath).ok()?;
+    let reader = reader.with_guessed_format().ok()?;
+    reader.decode().ok()
+}
+
+fn save_image(config: &Config, filepath: &PathBuf, hashes: &Mutex<HashSet<u64>>) -> SaveOutcome {
     log!(config.verbose, "Scan file: {}...", filepath.display());
 
     let reader = if let Ok(reader) = Reader::open(filepath) {
         reader
     } else {
-        return false;
+        return SaveOutcome::SkippedOther;
     };
     let reader = if let Ok(reader) = reader.with_guessed_format() {
         reader
     } else {
-        return false;
+        return SaveOutcome::SkippedOther;
     };
     let format = if let Some(format) = reader.format() {
         format
     } else {
-        return false;
+        return SaveOutcome::SkippedOther;
     };
     let image = if let Ok(image) = reader.decode() {
         image
     } else {
-        return false;
+        return SaveOutcome::SkippedOther;
     };
 
-    if image.width() < image.height() || image.width() < 800 || image.height() < 600 {
-        return false;
+    if image.width() < config.min_width || image.height() < config.min_height {
+        return SaveOutcome::SkippedTooSmall;
     }
 
+    let landscape = image.width() >= image.height();
+    if !matches_orientation(config.orientation, image.width(), image.height()) {
+        return SaveOutcome::SkippedWrongOrientation;
+    }
+
+    let hash = if config.dedupe {
+        let hash = dhash(&image);
+        let mut guard = hashes.lock().unwrap();
+        let is_duplicate = guard.iter().any(|existing| (hash ^ existing).count_ones() < config.distance);
+        if is_duplicate {
+            return SaveOutcome::SkippedDuplicate;
+        }
+
+        // Reserve the hash while still holding the lock so two threads racing on
+        // near-duplicate images from the same scan can't both pass the check above.
+        guard.insert(hash);
+        Some(hash)
+    } else {
+        None
+    };
+
     let ext = format.extensions_str().first().unwrap();
     let mut filename = String::from(filepath.file_name().unwrap().to_str().unwrap());
     filename.push_str(".");
     filename.push_str(*ext);
 
-    let target_file = config.target.join(filename);
+    let target_dir = if config.split_orientation {
+        config.target.join(if landscape { "landscape" } else { "portrait" })
+    } else {
+        config.target.clone()
+    };
+    if !config.dry_run && !target_dir.is_dir() && fs::create_dir_all(&target_dir).is_err() {
+        release_hash(hashes, hash);
+        return SaveOutcome::SkippedOther;
+    }
+
+    let target_file = target_dir.join(filename);
     if target_file.exists() {
-        return false;
+        release_hash(hashes, hash);
+        return SaveOutcome::SkippedOther;
+    }
+
+    if config.dry_run {
+        println!(
+            "Would save: {} -> {} ({:?}, {}x{})",
+            filepath.display(), target_file.display(), format, image.width(), image.height()
+        );
+        return SaveOutcome::Saved;
     }
 
     log!(config.verbose, "Saving image: {} ...", target_file.display());
 
-    fs::copy(filepath, target_file).is_ok()
+    if fs::copy(filepath, &target_file).is_ok() {
+        preserve_mtime(filepath, &target_file);
+        SaveOutcome::Saved
+    } else {
+        release_hash(hashes, hash);
+        SaveOutcome::SkippedOther
+    }
+}
+
+fn release_hash(hashes: &Mutex<HashSet<u64>>, hash: Option<u64>) {
+    if let Some(hash) = hash {
+        hashes.lock().unwrap().remove(&hash);
+    }
+}
+
+fn preserve_mtime(src: &PathBuf, dst: &PathBuf) {
+    if let Ok(metadata) = src.metadata() {
+        if let Ok(modified) = metadata.modified() {
+            let _ = set_file_mtime(dst, FileTime::from_system_time(modified));
+        }
+    }
 }
 
 fn archive_images(config: &Config) -> Result<(), Box<dyn Error>> {
@@ -179,37 +563,166 @@ fn archive_images(config: &Config) -> Result<(), Box<dyn Error>> {
 
     let timeline = Local::today() - Duration::days(365);
 
-    for entry in config.target.read_dir()? {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
+    let files: Vec<PathBuf> = target_scan_dirs(config).into_iter()
+        .filter_map(|dir| dir.read_dir().ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+
+    let archived = AtomicU32::new(0);
+
+    files.par_iter().try_for_each(|path| -> std::io::Result<()> {
+        let metadata = match path.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok(()),
+        };
 
-        if let Ok(metadata) = entry.metadata() {
-            let filetime = if let Ok(modified) = metadata.modified() {
-                modified
-            } else if let Ok(created) = metadata.created() {
-                created
-            } else {
-                continue;
-            };
-            let filedate = DateTime::from(filetime).date();
-
-            if filedate < timeline {    
-                log!(config.verbose, "archive file: {} ...", path.display());
-                
-                let year = filedate.format("%Y").to_string();
-                let dir = config.target.join(year);
-                if !dir.exists() {
-                    fs::create_dir(&dir)?;
+        let filetime = if let Ok(modified) = metadata.modified() {
+            modified
+        } else if let Ok(created) = metadata.created() {
+            created
+        } else {
+            return Ok(());
+        };
+        let filedate = DateTime::from(filetime).date();
+
+        if filedate < timeline {
+            let year = filedate.format("%Y").to_string();
+            let dir = path.parent().unwrap().join(year);
+            let bak_file = dir.join(path.file_name().unwrap());
+
+            if config.dry_run {
+                println!("Would archive: {} -> {}", path.display(), bak_file.display());
+                archived.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+
+            log!(config.verbose, "archive file: {} ...", path.display());
+
+            if !dir.exists() {
+                if let Err(e) = fs::create_dir(&dir) {
+                    if e.kind() != std::io::ErrorKind::AlreadyExists {
+                        return Err(e);
+                    }
                 }
-                let bak_file = dir.join(path.file_name().unwrap());
-                fs::copy(&path, &bak_file)?;
-                fs::remove_file(&path)?;
             }
+            fs::copy(path, &bak_file)?;
+            preserve_mtime(path, &bak_file);
+            fs::remove_file(path)?;
+            archived.fetch_add(1, Ordering::Relaxed);
         }
+
+        Ok(())
+    })?;
+
+    if config.dry_run {
+        println!("Dry run summary: {} would-archive", archived.load(Ordering::Relaxed));
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn test_config(target: PathBuf) -> Config {
+        Config {
+            target,
+            verbose: false,
+            archive: false,
+            watch: false,
+            threads: 1,
+            dedupe: false,
+            distance: 5,
+            min_width: 800,
+            min_height: 600,
+            orientation: Orientation::Any,
+            split_orientation: false,
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn orientation_from_str_parses_known_values() {
+        assert_eq!("landscape".parse::<Orientation>().unwrap(), Orientation::Landscape);
+        assert_eq!("Portrait".parse::<Orientation>().unwrap(), Orientation::Portrait);
+        assert_eq!("ANY".parse::<Orientation>().unwrap(), Orientation::Any);
+    }
+
+    #[test]
+    fn orientation_from_str_rejects_unknown_values() {
+        assert!("diagonal".parse::<Orientation>().is_err());
+    }
+
+    #[test]
+    fn matches_orientation_filters_by_aspect_ratio() {
+        assert!(matches_orientation(Orientation::Landscape, 1920, 1080));
+        assert!(!matches_orientation(Orientation::Landscape, 1080, 1920));
+        assert!(matches_orientation(Orientation::Portrait, 1080, 1920));
+        assert!(!matches_orientation(Orientation::Portrait, 1920, 1080));
+        assert!(matches_orientation(Orientation::Any, 1920, 1080));
+        assert!(matches_orientation(Orientation::Any, 1080, 1920));
+    }
+
+    #[test]
+    fn resolve_option_prefers_cli_then_file_then_default() {
+        assert_eq!(resolve_option(Some(1), Some(2), 3), 1);
+        assert_eq!(resolve_option(None, Some(2), 3), 2);
+        assert_eq!(resolve_option(None, None, 3), 3);
+    }
+
+    #[test]
+    fn dhash_is_zero_for_a_uniform_image() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(90, 80, Rgb([128, 128, 128])));
+        assert_eq!(dhash(&image), 0);
+    }
+
+    #[test]
+    fn dhash_sets_every_bit_for_a_strictly_decreasing_gradient() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(90, 80, |x, _y| {
+            let value = 255 - (x * 255 / 89) as u8;
+            Rgb([value, value, value])
+        }));
+        assert_eq!(dhash(&image), u64::MAX);
+    }
+
+    #[test]
+    fn save_image_rejects_images_below_the_minimum_resolution() {
+        let dir = std::env::temp_dir().join("spotlight_save_test_too_small");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("tiny.png");
+        RgbImage::from_pixel(10, 10, Rgb([255, 255, 255])).save(&source).unwrap();
+
+        let config = test_config(dir.clone());
+        let hashes = Mutex::new(HashSet::new());
+        let outcome = save_image(&config, &source, &hashes);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(outcome, SaveOutcome::SkippedTooSmall);
+    }
+
+    #[test]
+    fn save_image_saves_images_that_pass_the_filters() {
+        let dir = std::env::temp_dir().join("spotlight_save_test_saved");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let source = dir.join("big.png");
+        RgbImage::from_pixel(900, 700, Rgb([255, 255, 255])).save(&source).unwrap();
+
+        let config = test_config(dir.clone());
+        let hashes = Mutex::new(HashSet::new());
+        let outcome = save_image(&config, &source, &hashes);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(outcome, SaveOutcome::Saved);
+    }
+}